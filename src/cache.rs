@@ -44,26 +44,28 @@ impl<T, const N: usize> Default for Cache<T, N> {
 
 impl<T, const N: usize> Cache<T, N> {
     /// Insert an item in the cache
-    /// 
+    ///
     /// This item becomes most-recently-used item.
-    /// If the cache is full, the least-recently-used item will be removed.
-    pub(crate) fn insert(&mut self, val: T) {
+    /// If the cache is full, the least-recently-used item will be removed and
+    /// returned.
+    pub(crate) fn insert(&mut self, val: T) -> Option<T> {
         let entry = Entry {
             val,
             prev: 0,
             next: 0,
         };
 
-        let new_head = if self.entries.len() == self.entries.capacity() {
+        let (new_head, evicted) = if self.entries.len() == self.entries.capacity() {
             let i = self.pop_back();
-            self.entries[i as usize] = entry;
-            i
+            let evicted = core::mem::replace(&mut self.entries[i as usize], entry).val;
+            (i, Some(evicted))
         } else {
             self.entries.push(entry);
-            self.entries.len() as u16 -1
+            (self.entries.len() as u16 - 1, None)
         };
 
         self.push_front(new_head);
+        evicted
     }
 
     /// Touch a given entry, putting it first in the list
@@ -75,12 +77,6 @@ impl<T, const N: usize> Cache<T, N> {
         }
     }
 
-    /// Returns the number of elements in the cache
-    #[inline]
-    pub(crate) fn len(&self) -> usize {
-        self.entries.len()
-    }
-
     /// Evict all elements from the cache
     #[inline]
     pub(crate) fn clear(&mut self) {
@@ -125,12 +121,57 @@ impl<T, const N: usize> Cache<T, N> {
         old_tail
     }
 
+    /// Evict the least-recently-used entry and physically free its slot,
+    /// returning its value.
+    ///
+    /// Unlike evicting via [`Cache::insert`] (which immediately recycles
+    /// the freed slot for the entry being inserted, so the backing array
+    /// never shrinks), this actually removes the slot, so the evicted
+    /// value is dropped right away instead of sitting alive in the array
+    /// until some unrelated future insert happens to recycle it. Needed
+    /// when a caller evicts more than the one slot `insert` itself
+    /// recycles in a single operation (see `WeightedLRUMap::put`'s
+    /// budget-eviction loop).
+    ///
+    /// Freeing a slot other than the physically last one relocates the
+    /// last slot's entry into the freed one to keep the array
+    /// contiguous; when that happens, this returns the relocated entry's
+    /// new index so callers can update whatever external index (e.g. a
+    /// key -> slot map) still points to its old one.
+    pub(crate) fn evict_back(&mut self) -> (T, Option<u16>) {
+        let idx = self.pop_back();
+        let last = self.entries.len() as u16 - 1;
+        if idx == last {
+            return (self.entries.pop().expect("pop_back's tail exists").val, None);
+        }
+
+        self.entries.swap(idx as usize, last as usize);
+        let val = self.entries.pop().expect("pop_back's tail exists").val;
+
+        // The relocated entry now lives at `idx`; its neighbors (and
+        // head/tail, if it was either) still point at its old slot,
+        // `last`.
+        let prev = self.entries[idx as usize].prev;
+        let next = self.entries[idx as usize].next;
+        if self.head == last {
+            self.head = idx;
+        } else {
+            self.entries[prev as usize].next = idx;
+        }
+        if self.tail == last {
+            self.tail = idx;
+        } else {
+            self.entries[next as usize].prev = idx;
+        }
+        (val, Some(idx))
+    }
+
     /// Replace the item in the linked list.
     /// Returns the replaced item.
     pub(crate) fn replace(&mut self, idx: u16, val: T) -> T {
         self.touch_index(idx);
         let entry = &mut self.entries[idx as usize];
-        std::mem::replace(&mut entry.val, val)
+        core::mem::replace(&mut entry.val, val)
     }
 
     /// Touch the index and get the reference of the value
@@ -139,10 +180,50 @@ impl<T, const N: usize> Cache<T, N> {
         &self.entries[idx as usize].val
     }
 
+    /// Touch the index and get the mutable reference of the value
+    pub(crate) fn get_mut(&mut self, idx: u16) -> &mut T {
+        self.touch_index(idx);
+        &mut self.entries[idx as usize].val
+    }
+
     pub(crate) fn iter(&self) -> Iter<T, N> {
         Iter {
             cache: self,
             pos: self.head,
+            back_pos: self.tail,
+            done: self.entries.is_empty(),
+        }
+    }
+
+    /// Iterate from least- to most-recently-used, the reverse of [`Cache::iter`].
+    pub(crate) fn iter_lru(&self) -> IterRev<T, N> {
+        IterRev {
+            cache: self,
+            pos: self.tail,
+            back_pos: self.head,
+            done: self.entries.is_empty(),
+        }
+    }
+
+    /// Scan from most- to least-recently-used, returning the index of the
+    /// first entry matching `pred`.
+    ///
+    /// Lives here (rather than in `lib.rs`) because it walks `Entry::next`,
+    /// which is private to this module.
+    pub(crate) fn find_index(&self, pred: &mut impl FnMut(&T) -> bool) -> Option<u16> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let mut idx = self.head;
+        loop {
+            let entry = &self.entries[idx as usize];
+            if pred(&entry.val) {
+                return Some(idx);
+            }
+            if idx == self.tail {
+                return None;
+            }
+            idx = entry.next;
         }
     }
 }
@@ -164,19 +245,84 @@ where
 pub struct Iter<'a, T, const N: usize> {
     cache: &'a Cache<T, N>,
     pos: u16,
+    back_pos: u16,
+    done: bool,
 }
 
 impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<&'a T> {
-        let entry = self.cache.entries.get(self.pos as usize)?;
+        if self.done {
+            return None;
+        }
+        let entry = &self.cache.entries[self.pos as usize];
 
-        self.pos = if self.pos == self.cache.tail {
-            N as u16 // Point past the end of the array to signal we are done.
+        if self.pos == self.back_pos {
+            self.done = true;
         } else {
-            entry.next
-        };
+            self.pos = entry.next;
+        }
+        Some(&entry.val)
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.done {
+            return None;
+        }
+        let entry = &self.cache.entries[self.back_pos as usize];
+
+        if self.back_pos == self.pos {
+            self.done = true;
+        } else {
+            self.back_pos = entry.prev;
+        }
+        Some(&entry.val)
+    }
+}
+
+/// Iterator over values in an LRUCache, from least-recently-used to most-recently-used.
+///
+/// The reverse of [`Iter`]; see [`Cache::iter_lru`].
+pub struct IterRev<'a, T, const N: usize> {
+    cache: &'a Cache<T, N>,
+    pos: u16,
+    back_pos: u16,
+    done: bool,
+}
+
+impl<'a, T, const N: usize> Iterator for IterRev<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.done {
+            return None;
+        }
+        let entry = &self.cache.entries[self.pos as usize];
+
+        if self.pos == self.back_pos {
+            self.done = true;
+        } else {
+            self.pos = entry.prev;
+        }
+        Some(&entry.val)
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for IterRev<'a, T, N> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.done {
+            return None;
+        }
+        let entry = &self.cache.entries[self.back_pos as usize];
+
+        if self.back_pos == self.pos {
+            self.done = true;
+        } else {
+            self.back_pos = entry.next;
+        }
         Some(&entry.val)
     }
 }
\ No newline at end of file
@@ -0,0 +1,243 @@
+//! The key -> cache-slot index map backing `LRUMap`.
+//!
+//! On `std` builds this is a [`hashbrown::raw::RawTable`] storing only the
+//! `u16` cache slot per key: the key itself already lives in
+//! `cache.entries[idx].val.0`, so callers resolve hash collisions by reading
+//! it back out of the cache instead of this table keeping a second copy.
+//! This is the same trick `cached`'s `SizedCache` uses to avoid doubling key
+//! memory. Without `std` (and therefore without an allocator) `LRUMap` falls
+//! back to a fixed-capacity, array-backed map sized to the same `N` as the
+//! cache, the same trick `heapless`'s `FnvIndexMap` uses to stay
+//! allocation-free; there the key is cheap enough to just store inline.
+
+#[cfg(feature = "std")]
+mod hash_index {
+    use hashbrown::raw::RawTable;
+    use std::borrow::Borrow;
+    use std::hash::{BuildHasher, Hash, Hasher};
+    use std::marker::PhantomData;
+
+    /// Key -> cache-slot index that stores only the slot, not the key.
+    ///
+    /// Lookup, insertion, removal by key and iteration take an `entry_key`
+    /// callback that maps a candidate slot back to the key stored in the
+    /// cache at that slot; that's how this
+    /// table settles hash collisions and re-hashes existing entries without
+    /// ever holding a `K` of its own. `S` is the `BuildHasher` used to hash
+    /// keys, so callers can plug in a faster or DoS-resistant hasher instead
+    /// of the std-default `RandomState`.
+    pub(crate) struct HashIndex<K, S> {
+        table: RawTable<u16>,
+        hash_builder: S,
+        _marker: PhantomData<K>,
+    }
+
+    // `RawTable` doesn't implement `Debug`, so summarize it by length
+    // instead of deriving.
+    impl<K, S> std::fmt::Debug for HashIndex<K, S> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("HashIndex")
+                .field("table", &format_args!("RawTable {{ len: {} }}", self.table.len()))
+                .finish()
+        }
+    }
+
+    impl<K, S: Default> Default for HashIndex<K, S> {
+        fn default() -> Self {
+            HashIndex::with_hasher(S::default())
+        }
+    }
+
+    impl<K, S> HashIndex<K, S> {
+        /// Create an empty index that hashes keys with `hash_builder`.
+        pub(crate) fn with_hasher(hash_builder: S) -> Self {
+            HashIndex {
+                table: RawTable::new(),
+                hash_builder,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<K: Hash + Eq, S: BuildHasher> HashIndex<K, S> {
+        /// Hash `key` with this index's `BuildHasher`.
+        ///
+        /// Exposed so callers can remove a bucket by slot (see
+        /// [`HashIndex::remove_slot`]) once the key it was stored under is no
+        /// longer readable back out of the cache.
+        pub(crate) fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+            let mut hasher = self.hash_builder.build_hasher();
+            key.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        /// Look up the cache slot for `key`.
+        pub(crate) fn get<'a, Q>(&self, key: &Q, entry_key: impl Fn(u16) -> &'a K) -> Option<u16>
+        where
+            K: Borrow<Q> + 'a,
+            Q: Hash + Eq + ?Sized,
+        {
+            let hash = self.hash(key);
+            self.table
+                .get(hash, |&idx| entry_key(idx).borrow() == key)
+                .copied()
+        }
+
+        /// Record that `key` lives at cache slot `idx`, growing and
+        /// re-hashing the table if needed.
+        pub(crate) fn insert<'a>(&mut self, key: &K, idx: u16, entry_key: impl Fn(u16) -> &'a K)
+        where
+            K: 'a,
+        {
+            let hash_builder = &self.hash_builder;
+            let hash = {
+                let mut hasher = hash_builder.build_hasher();
+                key.hash(&mut hasher);
+                hasher.finish()
+            };
+            self.table.insert(hash, idx, |&existing| {
+                let mut hasher = hash_builder.build_hasher();
+                entry_key(existing).hash(&mut hasher);
+                hasher.finish()
+            });
+        }
+
+        /// Forget `key`, returning its cache slot if it was present.
+        pub(crate) fn remove<'a, Q>(&mut self, key: &Q, entry_key: impl Fn(u16) -> &'a K) -> Option<u16>
+        where
+            K: Borrow<Q> + 'a,
+            Q: Hash + Eq + ?Sized,
+        {
+            let hash = self.hash(key);
+            let bucket = self.table.find(hash, |&idx| entry_key(idx).borrow() == key)?;
+            // SAFETY: `bucket` was just returned by `find` on this table and
+            // nothing has mutated the table since.
+            // `RawTable::remove` also returns the freed `InsertSlot`, which
+            // we have no use for here.
+            let (idx, _slot) = unsafe { self.table.remove(bucket) };
+            Some(idx)
+        }
+
+        /// Forget whichever key is recorded as living at cache slot `idx`,
+        /// given that key's `hash`.
+        ///
+        /// Unlike [`HashIndex::remove`], this resolves the bucket by the
+        /// stored slot rather than by reading the key back out of the cache
+        /// — needed when that slot has already been overwritten (e.g. an
+        /// eviction reusing the freed slot) before the stale bucket is
+        /// cleaned up. Returns whether a bucket was removed.
+        pub(crate) fn remove_slot(&mut self, hash: u64, idx: u16) -> bool {
+            match self.table.find(hash, |&candidate| candidate == idx) {
+                Some(bucket) => {
+                    // SAFETY: `bucket` was just returned by `find` on this
+                    // table and nothing has mutated the table since.
+                    unsafe { self.table.remove(bucket) };
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Forget every key.
+        pub(crate) fn clear(&mut self) {
+            self.table.clear();
+        }
+
+        /// Number of keys currently recorded.
+        pub(crate) fn len(&self) -> usize {
+            self.table.len()
+        }
+
+        /// Visit every `(key, slot)` pair.
+        pub(crate) fn for_each<'a>(&self, entry_key: impl Fn(u16) -> &'a K, mut visit: impl FnMut(&'a K, u16))
+        where
+            K: 'a,
+        {
+            // SAFETY: we only read buckets the table currently considers
+            // live, and we don't mutate the table while iterating.
+            for bucket in unsafe { self.table.iter() } {
+                let idx = unsafe { *bucket.as_ref() };
+                visit(entry_key(idx), idx);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod array_index {
+    use arrayvec::ArrayVec;
+    use core::borrow::Borrow;
+
+    /// Fixed-capacity, allocation-free replacement for the `std` index map.
+    ///
+    /// Sized to the same `N` as the cache it indexes, so the whole `LRUMap`
+    /// lives inline with zero heap allocation. Lookups are a linear scan
+    /// over at most `N` entries, which is cheap at the small capacities this
+    /// crate targets, and simple enough not to need the hashing tricks the
+    /// `std` index map uses to avoid storing keys twice.
+    #[derive(Debug)]
+    pub(crate) struct ArrayIndexMap<K, const N: usize> {
+        entries: ArrayVec<(K, u16), N>,
+    }
+
+    impl<K, const N: usize> Default for ArrayIndexMap<K, N> {
+        fn default() -> Self {
+            ArrayIndexMap {
+                entries: ArrayVec::new(),
+            }
+        }
+    }
+
+    impl<K: Eq, const N: usize> ArrayIndexMap<K, N> {
+        /// Look up the cache slot for `key`.
+        pub(crate) fn get<Q>(&self, key: &Q) -> Option<u16>
+        where
+            K: Borrow<Q>,
+            Q: Eq + ?Sized,
+        {
+            self.entries
+                .iter()
+                .find(|(k, _)| k.borrow() == key)
+                .map(|(_, idx)| *idx)
+        }
+
+        /// Record that `key` lives at cache slot `idx`.
+        pub(crate) fn insert(&mut self, key: K, idx: u16) {
+            match self.entries.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = idx,
+                None => self.entries.push((key, idx)),
+            }
+        }
+
+        /// Forget `key`, returning its cache slot if it was present.
+        pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<u16>
+        where
+            K: Borrow<Q>,
+            Q: Eq + ?Sized,
+        {
+            let pos = self.entries.iter().position(|(k, _)| k.borrow() == key)?;
+            Some(self.entries.remove(pos).1)
+        }
+
+        /// Forget every key.
+        pub(crate) fn clear(&mut self) {
+            self.entries.clear();
+        }
+
+        /// Number of keys currently recorded.
+        pub(crate) fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// Iterate over all `(key, slot)` pairs.
+        pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &u16)> {
+            self.entries.iter().map(|(k, idx)| (k, idx))
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) use hash_index::HashIndex;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use array_index::ArrayIndexMap;
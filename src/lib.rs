@@ -1,54 +1,333 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
 mod cache;
+mod index_map;
+#[cfg(feature = "std")]
+mod weighted;
 
 use cache::*;
-use std::collections::HashMap;
-use std::hash::Hash;
+use core::borrow::Borrow;
+use core::hash::Hash;
+
+#[cfg(feature = "std")]
+use index_map::HashIndex;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::hash::BuildHasher;
 
-pub use cache::Iter;
+#[cfg(not(feature = "std"))]
+use index_map::ArrayIndexMap;
+
+pub use cache::{Iter, IterRev};
+#[cfg(feature = "std")]
+pub use weighted::WeightedLRUMap;
 
 #[cfg(test)]
 mod tests;
 
+/// Outcome of a [`LRUMap::put`] that displaced a previous entry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PutResult<K, T> {
+    /// The key already existed; this is the value it held before being overwritten.
+    Replaced(T),
+    /// The cache was full, so this key-value pair was evicted to make room.
+    Evicted(K, T),
+}
+
+/// LRU map
+///
+/// `S` is the [`BuildHasher`] used to hash keys, defaulting to
+/// [`RandomState`] like `std`'s own hash maps; swap it for a faster or
+/// DoS-resistant hasher tuned to your key distribution.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct LRUMap<K, T, const N: usize, S = RandomState> {
+    /// LRU Cache array
+    cache: Cache<(K, T), N>,
+    /// map to relate key and index in cache
+    indices: HashIndex<K, S>,
+}
+
 /// LRU map
+#[cfg(not(feature = "std"))]
 #[derive(Debug, Default)]
 pub struct LRUMap<K, T, const N: usize> {
     /// LRU Cache array
     cache: Cache<(K, T), N>,
     /// map to relate key and index in cache
-    indices: HashMap<K, u16>,
+    indices: ArrayIndexMap<K, N>,
+}
+
+#[cfg(feature = "std")]
+impl<K, T, const N: usize, S> LRUMap<K, T, N, S>
+where
+    K: Hash + Eq + Clone,
+    S: BuildHasher,
+{
+    /// Create an empty map that hashes keys with `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        LRUMap {
+            cache: Cache::default(),
+            indices: HashIndex::with_hasher(hasher),
+        }
+    }
+
+    /// Put a key-value pair
+    ///
+    /// Returns `None` if the key is new and the cache had room for it.
+    /// Returns `Some(PutResult::Replaced(old_value))` if the key already existed.
+    /// Returns `Some(PutResult::Evicted(key, value))` if the cache was full and
+    /// the least-recently-used entry was evicted to make room.
+    pub fn put(&mut self, key: K, value: T) -> Option<PutResult<K, T>> {
+        let entry_key = |idx: u16| &self.cache.entries[idx as usize].val.0;
+        match self.indices.get(&key, entry_key) {
+            None => {
+                // insert into cache and update the indices map
+                let evicted = self.cache.insert((key.clone(), value));
+                let new_idx = self.cache.head;
+                // `cache.insert` reuses the evicted entry's slot for the new
+                // one, so the key stored at `new_idx` has already been
+                // overwritten; remove the stale bucket by slot instead of by
+                // reading the (now-wrong) key back out of the cache.
+                let result = evicted.map(|(evicted_key, evicted_value)| {
+                    let hash = self.indices.hash(&evicted_key);
+                    self.indices.remove_slot(hash, new_idx);
+                    PutResult::Evicted(evicted_key, evicted_value)
+                });
+                let entry_key = |idx: u16| &self.cache.entries[idx as usize].val.0;
+                self.indices.insert(&key, new_idx, entry_key);
+                result
+            },
+            Some(idx) => {
+                // just replace the value
+                Some(PutResult::Replaced(self.cache.replace(idx, (key, value)).1))
+            }
+        }
+    }
+
+    /// get the key-value pair and touch it
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.indices.get(key, |idx| &self.cache.entries[idx as usize].val.0)?;
+        Some(&self.cache.get(idx).1)
+    }
+
+    /// Get the value for `key` without touching it, i.e. without changing
+    /// its recency.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.indices.get(key, |idx| &self.cache.entries[idx as usize].val.0)?;
+        Some(&self.cache.entries[idx as usize].val.1)
+    }
+
+    /// Get a mutable reference to the value for `key` and touch it.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.indices.get(key, |idx| &self.cache.entries[idx as usize].val.0)?;
+        Some(&mut self.cache.get_mut(idx).1)
+    }
+
+    /// Find the first value (scanning from most- to least-recently-used)
+    /// matching `pred`, touching it if found.
+    ///
+    /// Useful when the exact key isn't known but the value can be
+    /// recognized.
+    pub fn find<F>(&mut self, mut pred: F) -> Option<&T>
+    where
+        F: FnMut(&K, &T) -> bool,
+    {
+        let idx = self.cache.find_index(&mut |(k, v)| pred(k, v))?;
+        self.cache.touch_index(idx);
+        Some(&self.cache.entries[idx as usize].val.1)
+    }
+
+    /// Remove a key
+    pub fn remove_one<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.indices.remove(key, |idx| &self.cache.entries[idx as usize].val.0);
+        if let Some(idx) = idx {
+            self.cache.remove(idx);
+        }
+    }
+
+    /// Remove keys which match the predicate.
+    pub fn remove<F>(&mut self, mut pred: F)
+    where
+        F: FnMut(&K) -> bool
+    {
+        // Collect matching keys first so we aren't mutating `indices` while
+        // iterating it. A `Vec` (rather than a fixed-capacity `ArrayVec<_,
+        // N>`) is correct here even though the index map should never hold
+        // more than `N` live keys: if it ever did (e.g. a latent bug like a
+        // stale bucket left behind by a buggy eviction), an unchecked
+        // `ArrayVec::push` would panic on an otherwise-valid call.
+        let mut matched: Vec<K> = Vec::new();
+        self.indices.for_each(
+            |idx| &self.cache.entries[idx as usize].val.0,
+            |key, _| {
+                if pred(key) {
+                    matched.push(key.clone());
+                }
+            },
+        );
+        for key in matched {
+            let idx = self.indices.remove(&key, |idx| &self.cache.entries[idx as usize].val.0);
+            if let Some(idx) = idx {
+                self.cache.remove(idx);
+            }
+        }
+    }
+
+    /// Clear the LRU Cache
+    #[inline]
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.cache.clear();
+    }
+
+    /// Number of items
+    ///
+    /// Backed by `indices.len()`, not `cache.entries.len()`: `Cache::remove` only
+    /// unlinks an entry from the LRU list, it never shrinks the backing
+    /// array, so the array's length overcounts once anything has been
+    /// removed without a matching insert to recycle its slot.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Touch the keys which match the predicate.
+    pub fn touch<F>(&mut self, mut pred: F)
+    where
+        F: FnMut(&K) -> bool
+    {
+        // See `remove`'s comment on why this collects into a `Vec` rather
+        // than a fixed-capacity `ArrayVec<_, N>`.
+        let mut matched: Vec<u16> = Vec::new();
+        self.indices.for_each(
+            |idx| &self.cache.entries[idx as usize].val.0,
+            |key, idx| {
+                if pred(key) {
+                    matched.push(idx);
+                }
+            },
+        );
+        for idx in matched {
+            self.cache.touch_index(idx);
+        }
+    }
+
+    /// Iterator for keys and values
+    pub fn iter(&self) -> Iter<(K, T), N> {
+        self.cache.iter()
+    }
+
+    /// Iterator for keys and values, from least- to most-recently-used.
+    pub fn iter_lru(&self) -> IterRev<(K, T), N> {
+        self.cache.iter_lru()
+    }
 }
 
+#[cfg(not(feature = "std"))]
 impl<K, T, const N: usize> LRUMap<K, T, N>
 where
     K:  Hash + Eq + Clone
 {
     /// Put a key-value pair
-    /// Returns the old value if the key exists, otherwise returns None
-    pub fn put(&mut self, key: K, value: T) -> Option<T> {
+    ///
+    /// Returns `None` if the key is new and the cache had room for it.
+    /// Returns `Some(PutResult::Replaced(old_value))` if the key already existed.
+    /// Returns `Some(PutResult::Evicted(key, value))` if the cache was full and
+    /// the least-recently-used entry was evicted to make room.
+    pub fn put(&mut self, key: K, value: T) -> Option<PutResult<K, T>> {
         match self.indices.get(&key) {
             None => {
                 // insert into cache and update the indices map
-                self.cache.insert((key.clone(), value));
+                let evicted = self.cache.insert((key.clone(), value));
+                // Forget the evicted key's bucket before recording the new
+                // key, same as the `std` path: `ArrayIndexMap::insert`
+                // unconditionally pushes for a new key, so inserting first
+                // would overflow the fixed-capacity backing `ArrayVec`.
+                let result = evicted.map(|(evicted_key, evicted_value)| {
+                    self.indices.remove(&evicted_key);
+                    PutResult::Evicted(evicted_key, evicted_value)
+                });
                 self.indices.insert(key, self.cache.head);
-                None
+                result
             },
             Some(idx) => {
                 // just replace the value
-                Some(self.cache.replace(*idx, (key, value)).1)
+                Some(PutResult::Replaced(self.cache.replace(idx, (key, value)).1))
             }
         }
     }
 
     /// get the key-value pair and touch it
-    pub fn get(&mut self, key: &K) -> Option<&T> {
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
         match self.indices.get(key) {
             None => None,
-            Some(idx) => Some(&self.cache.get(*idx).1)
+            Some(idx) => Some(&self.cache.get(idx).1)
         }
     }
 
+    /// Get the value for `key` without touching it, i.e. without changing
+    /// its recency.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let idx = self.indices.get(key)?;
+        Some(&self.cache.entries[idx as usize].val.1)
+    }
+
+    /// Get a mutable reference to the value for `key` and touch it.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut T>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let idx = self.indices.get(key)?;
+        Some(&mut self.cache.get_mut(idx).1)
+    }
+
+    /// Find the first value (scanning from most- to least-recently-used)
+    /// matching `pred`, touching it if found.
+    ///
+    /// Useful when the exact key isn't known but the value can be
+    /// recognized.
+    pub fn find<F>(&mut self, mut pred: F) -> Option<&T>
+    where
+        F: FnMut(&K, &T) -> bool,
+    {
+        let idx = self.cache.find_index(&mut |(k, v)| pred(k, v))?;
+        self.cache.touch_index(idx);
+        Some(&self.cache.entries[idx as usize].val.1)
+    }
+
     /// Remove a key
-    pub fn remove_one(&mut self, key: &K) {
+    pub fn remove_one<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
         if let Some(idx) = self.indices.remove(key) {
             self.cache.remove(idx);
         }
@@ -59,13 +338,17 @@ where
     where
         F: FnMut(&K) -> bool
     {
-        // make a new hashmap and replace the old one to make the borrow checker happy
-        let old_indices = std::mem::replace(&mut self.indices, HashMap::new());
-        for (key, idx) in old_indices.into_iter() {
-            if pred(&key) {
+        // collect matching keys first so we aren't mutating `indices` while
+        // iterating it; bounded by `N` so this never allocates on the heap
+        let mut matched: arrayvec::ArrayVec<K, N> = arrayvec::ArrayVec::new();
+        for (key, _) in self.indices.iter() {
+            if pred(key) {
+                matched.push(key.clone());
+            }
+        }
+        for key in matched {
+            if let Some(idx) = self.indices.remove(&key) {
                 self.cache.remove(idx);
-            } else {
-                self.indices.insert(key, idx);
             }
         }
     }
@@ -78,9 +361,14 @@ where
     }
 
     /// Number of items
+    ///
+    /// Backed by `indices.len()`, not `cache.entries.len()`: `Cache::remove` only
+    /// unlinks an entry from the LRU list, it never shrinks the backing
+    /// array, so the array's length overcounts once anything has been
+    /// removed without a matching insert to recycle its slot.
     #[inline]
     pub fn len(&self) -> usize {
-        self.cache.len()
+        self.indices.len()
     }
 
     /// Touch the keys which match the predicate.
@@ -99,4 +387,9 @@ where
     pub fn iter(&self) -> Iter<(K, T), N> {
         self.cache.iter()
     }
+
+    /// Iterator for keys and values, from least- to most-recently-used.
+    pub fn iter_lru(&self) -> IterRev<(K, T), N> {
+        self.cache.iter_lru()
+    }
 }
@@ -1,5 +1,14 @@
 use super::*;
 
+// `no_std` builds drop `Vec`/`String` from the prelude; pull them back in
+// from `alloc` for these tests. This is sound even without a
+// `#[global_allocator]` of our own because the test harness itself links
+// `std`, which provides one.
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, string::ToString, vec::Vec};
+
 type TestMap = LRUMap<&'static str, i32, 4>;
 
 fn items<K, T, const N: usize>(map: &mut LRUMap<K, T, N>) -> Vec<(K, T)>
@@ -21,10 +30,34 @@ fn get_untouch<T, const N: usize>(cache: &Cache<T, N>, idx: u16) -> &T {
 }
 
 /// Check whether the keys in `indices` match the keys in `cache`
-/// Used for testing.  
+/// Used for testing.
+#[cfg(feature = "std")]
 fn check_keys<K, T, const N: usize>(map: &LRUMap<K, T, N>) -> bool
 where
-    K: Hash + Eq + Clone, 
+    K: Hash + Eq + Clone,
+    T: Clone
+{
+    let mut ok = true;
+    map.indices.for_each(
+        |idx| &get_untouch(&map.cache, idx).0,
+        |key, idx| {
+            // The std index stores no keys, so check that the key read out
+            // of each recorded slot resolves back to that same slot.
+            let found = map.indices.get(key, |i| &get_untouch(&map.cache, i).0);
+            if found != Some(idx) {
+                ok = false;
+            }
+        },
+    );
+    ok
+}
+
+/// Check whether the keys in `indices` match the keys in `cache`
+/// Used for testing.
+#[cfg(not(feature = "std"))]
+fn check_keys<K, T, const N: usize>(map: &LRUMap<K, T, N>) -> bool
+where
+    K: Hash + Eq + Clone,
     T: Clone
 {
     for (key, idx) in map.indices.iter() {
@@ -59,7 +92,7 @@ fn put() {
     );
     assert!(check_keys(&cache), "check keys");
 
-    assert_eq!(cache.put("5", 5), None);
+    assert_eq!(cache.put("5", 5), Some(PutResult::Evicted("1", 1)));
     assert_eq!(cache.len(), 4);
     assert_eq!(
         items(&mut cache),
@@ -67,17 +100,17 @@ fn put() {
         "Least-recently-used item evicted."
     );
 
-    assert_eq!(cache.put("6", 6), None);
-    assert_eq!(cache.put("7", 7), None);
-    assert_eq!(cache.put("8", 8), None);
-    assert_eq!(cache.put("9", 9), None);
+    assert_eq!(cache.put("6", 6), Some(PutResult::Evicted("2", 2)));
+    assert_eq!(cache.put("7", 7), Some(PutResult::Evicted("3", 3)));
+    assert_eq!(cache.put("8", 8), Some(PutResult::Evicted("4", 4)));
+    assert_eq!(cache.put("9", 9), Some(PutResult::Evicted("5", 5)));
     assert_eq!(
         items(&mut cache),
         [("9", 9), ("8", 8), ("7", 7), ("6", 6)],
         "Least-recently-used item evicted."
     );
 
-    assert_eq!(cache.put("7", 14), Some(7));
+    assert_eq!(cache.put("7", 14), Some(PutResult::Replaced(7)));
     assert_eq!(
         items(&mut cache),
         [("7", 14), ("9", 9), ("8", 8), ("6", 6)],
@@ -85,6 +118,77 @@ fn put() {
     );
 }
 
+#[test]
+fn evicted_key_is_forgotten() {
+    let mut cache = TestMap::default();
+
+    assert_eq!(cache.put("1", 1), None);
+    assert_eq!(cache.put("2", 2), None);
+    assert_eq!(cache.put("3", 3), None);
+    assert_eq!(cache.put("4", 4), None);
+    assert!(check_keys(&cache), "check keys");
+
+    assert_eq!(cache.put("5", 5), Some(PutResult::Evicted("1", 1)));
+    assert!(check_keys(&cache), "check keys after eviction");
+
+    // The evicted key must no longer be reachable through the map.
+    assert_eq!(cache.get(&"1"), None, "evicted key should be forgotten");
+    assert_eq!(cache.len(), 4);
+    assert_eq!(
+        items(&mut cache),
+        [("5", 5), ("4", 4), ("3", 3), ("2", 2)],
+        "evicted key did not disturb the remaining order"
+    );
+}
+
+#[test]
+fn check_keys_after_many_evictions() {
+    let mut cache: LRUMap<i32, i32, 4> = LRUMap::default();
+    for i in 0..100 {
+        cache.put(i, i * i);
+        assert!(check_keys(&cache), "check keys after put({})", i);
+    }
+    assert_eq!(cache.len(), 4);
+    for i in 96..100 {
+        assert_eq!(cache.get(&i), Some(&(i * i)));
+    }
+    for i in 0..96 {
+        assert_eq!(cache.get(&i), None, "evicted key {} should be forgotten", i);
+    }
+}
+
+/// Count the buckets actually stored in `map.indices`, independent of
+/// `map.cache` (whose slots an evicted bucket might still, wrongly, resolve
+/// into). Used to catch index-map bugs that `check_keys` can't see, since a
+/// stale bucket reads back whatever key now occupies its slot, and that key
+/// still resolves to the same slot.
+#[cfg(feature = "std")]
+fn index_count<K, T, const N: usize>(map: &LRUMap<K, T, N>) -> usize
+where
+    K: Hash + Eq + Clone,
+{
+    let mut count = 0;
+    map.indices.for_each(
+        |idx| &get_untouch(&map.cache, idx).0,
+        |_, _| count += 1,
+    );
+    count
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn indices_stay_bounded_after_many_evictions() {
+    let mut cache: LRUMap<i32, i32, 4> = LRUMap::default();
+    for i in 0..100 {
+        cache.put(i, i * i);
+    }
+    assert_eq!(
+        index_count(&cache),
+        4,
+        "evicted keys must not leak stale buckets in the index map"
+    );
+}
+
 #[test]
 fn cache_hit() {
     let mut cache = TestMap::default();
@@ -122,6 +226,137 @@ fn cache_hit() {
     );
 }
 
+#[test]
+#[cfg(feature = "std")]
+fn custom_hasher() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+
+    let mut cache: LRUMap<&'static str, i32, 4, BuildHasherDefault<DefaultHasher>> =
+        LRUMap::with_hasher(BuildHasherDefault::default());
+
+    assert_eq!(cache.put("1", 1), None);
+    assert_eq!(cache.put("2", 2), None);
+    assert_eq!(cache.get(&"1"), Some(&1));
+    assert_eq!(cache.get(&"2"), Some(&2));
+    assert_eq!(cache.get(&"3"), None);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn weighted_put_evicts_by_budget() {
+    let mut cache: WeightedLRUMap<&'static str, Vec<u8>, 4, _> =
+        WeightedLRUMap::new(10, |v: &Vec<u8>| v.len());
+
+    assert_eq!(cache.put("1", vec![0; 4]), None);
+    assert_eq!(cache.put("2", vec![0; 4]), None);
+    assert_eq!(cache.total_weight(), 8);
+    assert_eq!(cache.len(), 2);
+
+    // Admitting "3" needs to evict the least-recently-used "1".
+    assert_eq!(cache.put("3", vec![0; 4]), None);
+    assert_eq!(cache.total_weight(), 8);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&"1").is_none(), "\"1\" should have been evicted");
+    assert!(cache.get(&"2").is_some());
+    assert!(cache.get(&"3").is_some());
+
+    // A single item heavier than the whole budget evicts everything else.
+    assert_eq!(cache.put("huge", vec![0; 20]), None);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.total_weight(), 20);
+
+    // Replacing a key adjusts the weight by the delta.
+    cache.clear();
+    assert_eq!(cache.total_weight(), 0);
+    cache.put("1", vec![0; 4]);
+    assert_eq!(cache.put("1", vec![0; 2]), Some(vec![0; 4]));
+    assert_eq!(cache.total_weight(), 2);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn weighted_eviction_frees_backing_slots_immediately() {
+    // Fill the backing array to its physical capacity (N = 4) first, with
+    // nothing evicted yet.
+    let mut cache: WeightedLRUMap<&'static str, Vec<u8>, 4, _> =
+        WeightedLRUMap::new(20, |v: &Vec<u8>| v.len());
+    cache.put("1", vec![0; 2]);
+    cache.put("2", vec![0; 2]);
+    cache.put("3", vec![0; 2]);
+    cache.put("4", vec![0; 2]);
+    assert_eq!(cache.total_weight(), 8);
+
+    // Admitting "huge" only needs to evict "1" and "2" to fit the budget.
+    // Each budget eviction frees its backing slot immediately, so unlike a
+    // leaky implementation that only unlinks evicted entries without ever
+    // shrinking the array, `cache.insert` below finds room already waiting
+    // for it and does *not* additionally evict "3" to recycle a slot.
+    assert_eq!(cache.put("huge", vec![0; 15]), None);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.total_weight(), 19);
+    for key in ["1", "2"] {
+        assert!(cache.get(&key).is_none(), "\"{}\" should have been evicted", key);
+    }
+    assert_eq!(cache.get(&"3"), Some(&vec![0; 2]), "\"3\" should survive");
+    assert_eq!(cache.get(&"4"), Some(&vec![0; 2]), "\"4\" should survive");
+    assert_eq!(cache.get(&"huge"), Some(&vec![0; 15]));
+}
+
+#[test]
+fn peek_get_mut_and_find() {
+    let mut cache = TestMap::default();
+    cache.put("1", 1);
+    cache.put("2", 2);
+    cache.put("3", 3);
+
+    // peek reads without reordering.
+    assert_eq!(cache.peek(&"1"), Some(&1));
+    assert_eq!(
+        items(&mut cache),
+        [("3", 3), ("2", 2), ("1", 1)],
+        "peek must not touch"
+    );
+
+    // get_mut touches and lets the caller mutate in place.
+    if let Some(v) = cache.get_mut(&"1") {
+        *v = 10;
+    }
+    assert_eq!(
+        items(&mut cache),
+        [("1", 10), ("3", 3), ("2", 2)],
+        "get_mut touches the matching item"
+    );
+
+    // find scans by predicate and touches the first match.
+    assert_eq!(cache.find(|_, v| *v == 3), Some(&3));
+    assert_eq!(
+        items(&mut cache),
+        [("3", 3), ("1", 10), ("2", 2)],
+        "find touches the matching item"
+    );
+    assert_eq!(cache.find(|_, v| *v == 100), None);
+}
+
+#[test]
+fn borrowed_lookup() {
+    let mut cache: LRUMap<String, i32, 4> = LRUMap::default();
+    cache.put("1".to_string(), 1);
+    cache.put("2".to_string(), 2);
+
+    // All of these take `&str`, not `&String`.
+    assert_eq!(cache.get("1"), Some(&1));
+    assert_eq!(cache.peek("2"), Some(&2));
+    if let Some(v) = cache.get_mut("1") {
+        *v = 10;
+    }
+    assert_eq!(cache.peek("1"), Some(&10));
+
+    cache.remove_one("2");
+    assert_eq!(cache.get("2"), None, "removed key should be forgotten");
+    assert_eq!(cache.len(), 1);
+}
+
 #[test]
 fn clear() {
     let mut cache = TestMap::default();
@@ -139,3 +374,38 @@ fn clear() {
     cache.clear();
     assert_eq!(items(&mut cache), [], "all items evicted again");
 }
+
+#[test]
+fn iter_lru_is_reverse_of_iter() {
+    let mut cache = TestMap::default();
+    cache.put("1", 1);
+    cache.put("2", 2);
+    cache.put("3", 3);
+
+    let mru_first: Vec<_> = cache.iter().collect();
+    let mut lru_first: Vec<_> = cache.iter_lru().collect();
+    lru_first.reverse();
+    assert_eq!(mru_first, lru_first);
+
+    let mut rev: Vec<_> = cache.iter_lru().collect();
+    assert_eq!(rev, [&("1", 1), &("2", 2), &("3", 3)]);
+    rev.reverse();
+    assert_eq!(rev, [&("3", 3), &("2", 2), &("1", 1)]);
+}
+
+#[test]
+fn iter_is_double_ended() {
+    let mut cache = TestMap::default();
+    cache.put("1", 1);
+    cache.put("2", 2);
+    cache.put("3", 3);
+
+    // Walking from both ends at once should meet in the middle without
+    // repeating or skipping the entry where the cursors cross.
+    let mut iter = cache.iter();
+    assert_eq!(iter.next(), Some(&("3", 3)));
+    assert_eq!(iter.next_back(), Some(&("1", 1)));
+    assert_eq!(iter.next(), Some(&("2", 2)));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
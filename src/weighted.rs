@@ -0,0 +1,131 @@
+//! A weight- (cost-) based variant of [`crate::LRUMap`].
+//!
+//! Only available with the `std` feature: it's built on `HashMap` rather
+//! than the fixed-capacity index map the `no_std` build uses.
+
+use crate::cache::Cache;
+use crate::cache::{Iter, IterRev};
+use core::hash::Hash;
+use std::collections::HashMap;
+
+/// LRU map that evicts by a weight budget instead of [`crate::LRUMap`]'s
+/// fixed entry count.
+///
+/// Each value's cost is computed by a `weigher: Fn(&T) -> usize` (e.g. byte
+/// size). `put` keeps evicting the least-recently-used entry until the
+/// cache's total weight is back under `budget`. `N` still bounds the number
+/// of slots so the backing `ArrayVec` never overflows; a single item heavier
+/// than the whole budget evicts everything else and is kept on its own.
+#[derive(Debug)]
+pub struct WeightedLRUMap<K, T, const N: usize, W> {
+    cache: Cache<(K, T), N>,
+    indices: HashMap<K, u16>,
+    weigher: W,
+    budget: usize,
+    total_weight: usize,
+}
+
+impl<K, T, const N: usize, W> WeightedLRUMap<K, T, N, W>
+where
+    K: Hash + Eq + Clone,
+    W: Fn(&T) -> usize,
+{
+    /// Create an empty map that evicts to keep the total weight of its
+    /// values under `budget`, as computed by `weigher`.
+    pub fn new(budget: usize, weigher: W) -> Self {
+        WeightedLRUMap {
+            cache: Cache::default(),
+            indices: HashMap::new(),
+            weigher,
+            budget,
+            total_weight: 0,
+        }
+    }
+
+    /// Put a key-value pair.
+    ///
+    /// If `key` already existed, its value is replaced and returned, with
+    /// `total_weight` adjusted by the difference in weight. Otherwise the
+    /// least-recently-used entries are evicted until there is room for
+    /// `value`'s weight (even if that means evicting everything else), and
+    /// `None` is returned.
+    pub fn put(&mut self, key: K, value: T) -> Option<T> {
+        let new_weight = (self.weigher)(&value);
+
+        if let Some(&idx) = self.indices.get(&key) {
+            let old_weight = (self.weigher)(&self.cache.entries[idx as usize].val.1);
+            let (_, old_value) = self.cache.replace(idx, (key, value));
+            self.total_weight = self.total_weight + new_weight - old_weight;
+            return Some(old_value);
+        }
+
+        // Evict least-recently-used entries until `value` fits the budget
+        // (even if that means evicting everything else). `evict_back`
+        // physically frees each evicted slot — unlike `cache.insert`'s own
+        // implicit eviction below, which just recycles a slot for the
+        // entry being inserted — so the evicted value is dropped here
+        // instead of sitting alive in the backing array until some
+        // unrelated future insert happens to recycle it.
+        while !self.indices.is_empty() && self.total_weight + new_weight > self.budget {
+            let (evicted, relocated) = self.cache.evict_back();
+            self.total_weight -= (self.weigher)(&evicted.1);
+            self.indices.remove(&evicted.0);
+            if let Some(new_idx) = relocated {
+                let relocated_key = self.cache.entries[new_idx as usize].val.0.clone();
+                self.indices.insert(relocated_key, new_idx);
+            }
+        }
+
+        if let Some((evicted_key, evicted_value)) = self.cache.insert((key.clone(), value)) {
+            self.indices.remove(&evicted_key);
+            self.total_weight -= (self.weigher)(&evicted_value);
+        }
+        self.indices.insert(key, self.cache.head);
+        self.total_weight += new_weight;
+        None
+    }
+
+    /// Get the value for `key` and touch it.
+    pub fn get(&mut self, key: &K) -> Option<&T> {
+        let idx = *self.indices.get(key)?;
+        Some(&self.cache.get(idx).1)
+    }
+
+    /// Remove a key.
+    pub fn remove_one(&mut self, key: &K) {
+        if let Some(idx) = self.indices.remove(key) {
+            self.total_weight -= (self.weigher)(&self.cache.entries[idx as usize].val.1);
+            self.cache.remove(idx);
+        }
+    }
+
+    /// Clear the map.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.cache.clear();
+        self.total_weight = 0;
+    }
+
+    /// Number of items currently cached.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Total weight of all cached values, as computed by `weigher`.
+    #[inline]
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    /// Iterator for keys and values, from most- to least-recently-used.
+    pub fn iter(&self) -> Iter<(K, T), N> {
+        self.cache.iter()
+    }
+
+    /// Iterator for keys and values, from least- to most-recently-used.
+    pub fn iter_lru(&self) -> IterRev<(K, T), N> {
+        self.cache.iter_lru()
+    }
+}